@@ -1,16 +1,21 @@
 // Imports
 // -------
 
+mod mft;
+
 use std::{
+    collections::HashMap,
     ffi::c_void,
     io,
     iter::once,
+    marker::PhantomData,
     os::windows::prelude::OsStrExt,
     path::{Path, PathBuf},
     ptr::null_mut,
+    sync::atomic::{AtomicBool, Ordering},
 };
 
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
 use jwalk::WalkDir;
 use parse_display::{Display, FromStr};
 use winapi::um::{
@@ -19,20 +24,32 @@ use winapi::um::{
         CreateFileW, SetFileInformationByHandle, SetFileTime, FILE_BASIC_INFO, OPEN_EXISTING,
     },
     handleapi::{CloseHandle, INVALID_HANDLE_VALUE},
-    minwinbase::FileBasicInfo,
+    minwinbase::{FileAttributeTagInfo, FileBasicInfo},
     winbase::GetFileInformationByHandleEx,
-    winbase::FILE_FLAG_BACKUP_SEMANTICS,
+    winbase::{FILE_ATTRIBUTE_TAG_INFO, FILE_FLAG_BACKUP_SEMANTICS, FILE_FLAG_OPEN_REPARSE_POINT},
     winnt::{FILE_SHARE_READ, HANDLE, LARGE_INTEGER},
 };
 use winapi::{
     shared::minwindef::FILETIME,
-    um::winnt::{FILE_READ_ATTRIBUTES, FILE_WRITE_ATTRIBUTES},
+    um::winnt::{
+        FILE_ATTRIBUTE_ARCHIVE, FILE_ATTRIBUTE_COMPRESSED, FILE_ATTRIBUTE_DIRECTORY,
+        FILE_ATTRIBUTE_ENCRYPTED, FILE_ATTRIBUTE_HIDDEN, FILE_ATTRIBUTE_NORMAL,
+        FILE_ATTRIBUTE_NOT_CONTENT_INDEXED, FILE_ATTRIBUTE_OFFLINE, FILE_ATTRIBUTE_READONLY,
+        FILE_ATTRIBUTE_REPARSE_POINT, FILE_ATTRIBUTE_SPARSE_FILE, FILE_ATTRIBUTE_SYSTEM,
+        FILE_ATTRIBUTE_TEMPORARY, FILE_READ_ATTRIBUTES, FILE_WRITE_ATTRIBUTES,
+        IO_REPARSE_TAG_MOUNT_POINT, IO_REPARSE_TAG_SYMLINK,
+    },
 };
 // -------
 
 // Shared Win32 wrappers
 // ---------------------
 
+// When false (the default), handles are opened with FILE_FLAG_OPEN_REPARSE_POINT
+// so a symlink/junction refers to the link itself rather than its target.
+// `--follow` flips this back to the old, target-following behavior.
+static FOLLOW_REPARSE: AtomicBool = AtomicBool::new(false);
+
 unsafe fn make_large_integer(v: i64) -> LARGE_INTEGER {
     let mut ret: LARGE_INTEGER = std::mem::zeroed();
     *ret.QuadPart_mut() = v;
@@ -54,13 +71,20 @@ unsafe fn win32_open_file(path: &Path, mode: Win32OpenMode) -> HANDLE {
         Win32OpenMode::Write => FILE_WRITE_ATTRIBUTES,
     };
 
+    // Unless following was requested, operate on the link itself so reparse
+    // points don't silently redirect us to their target.
+    let mut flags = FILE_FLAG_BACKUP_SEMANTICS;
+    if !FOLLOW_REPARSE.load(Ordering::Relaxed) {
+        flags |= FILE_FLAG_OPEN_REPARSE_POINT;
+    }
+
     let handle = CreateFileW(
         os_path.as_ptr(),
         access,
         FILE_SHARE_READ,
         null_mut(),
         OPEN_EXISTING,
-        FILE_FLAG_BACKUP_SEMANTICS,
+        flags,
         null_mut(),
     );
     if handle == INVALID_HANDLE_VALUE {
@@ -107,10 +131,56 @@ unsafe fn get_file_basic_info(path: &Path) -> Option<FILE_BASIC_INFO> {
     Some(ret)
 }
 
-unsafe fn set_file_basic_info(path: &Path, mut fi: FILE_BASIC_INFO) {
+// Reads the timestamps and the reparse tag from a single handle, so the common
+// (non-reparse) case doesn't pay a second CreateFileW/SetFileTime round trip.
+// For a regular file the tag is `0`.
+unsafe fn get_file_basic_and_tag(path: &Path) -> Option<(FILE_BASIC_INFO, FILE_ATTRIBUTE_TAG_INFO)> {
+    let mut basic: FILE_BASIC_INFO = std::mem::zeroed();
+    let mut tag: FILE_ATTRIBUTE_TAG_INFO = std::mem::zeroed();
+
+    let handle = win32_open_file(path, Win32OpenMode::Read);
+    if handle == INVALID_HANDLE_VALUE {
+        return None;
+    }
+
+    if GetFileInformationByHandleEx(
+        handle,
+        FileBasicInfo,
+        &mut basic as *mut _ as *mut c_void,
+        std::mem::size_of::<FILE_BASIC_INFO>().try_into().unwrap(),
+    ) == 0
+    {
+        let err = GetLastError();
+        CloseHandle(handle);
+        eprintln!("{}: error retrieving timestamps: {}", path.display(), err);
+        return None;
+    }
+
+    // A failed tag query leaves the zeroed (`NONE`) tag rather than dropping the
+    // whole record.
+    if GetFileInformationByHandleEx(
+        handle,
+        FileAttributeTagInfo,
+        &mut tag as *mut _ as *mut c_void,
+        std::mem::size_of::<FILE_ATTRIBUTE_TAG_INFO>()
+            .try_into()
+            .unwrap(),
+    ) == 0
+    {
+        eprintln!(
+            "{}: error retrieving reparse tag: {}",
+            path.display(),
+            GetLastError()
+        );
+    }
+    CloseHandle(handle);
+    Some((basic, tag))
+}
+
+unsafe fn set_file_basic_info(path: &Path, mut fi: FILE_BASIC_INFO) -> io::Result<()> {
     let handle = win32_open_file(path, Win32OpenMode::Write);
     if handle == INVALID_HANDLE_VALUE {
-        return;
+        return Err(io::Error::last_os_error());
     }
 
     let valid = SetFileInformationByHandle(
@@ -121,9 +191,9 @@ unsafe fn set_file_basic_info(path: &Path, mut fi: FILE_BASIC_INFO) {
     );
     CloseHandle(handle);
     if valid == 0 {
-        let err = GetLastError();
-        eprintln!("{}: error applying timestamps: {}", path.display(), err);
+        return Err(io::Error::last_os_error());
     }
+    Ok(())
 }
 
 // ---------------------
@@ -137,16 +207,74 @@ trait Timestamps: std::fmt::Debug + std::fmt::Display + std::str::FromStr {
     fn version() -> i32;
     fn header() -> &'static str;
     fn get(path: &Path) -> Option<Self>;
-    fn set(self, path: &Path);
+
+    /// Restores the record onto `path`, returning an error (to be collected and
+    /// reported by `apply`) rather than aborting when a file is locked,
+    /// unreadable, or its row was malformed.
+    fn set(self, path: &Path) -> io::Result<()>;
+
+    /// Builds a record straight from the four raw FILETIME values (100-ns ticks
+    /// since 1601), as recovered from the MFT or `FILE_BASIC_INFO`.
+    fn from_filetimes(created: i64, modified: i64, changed: i64, accessed: i64) -> Self
+    where
+        Self: Sized;
+
+    /// JSON object key for each timestamp column, in the same order as the TSV
+    /// columns.
+    fn field_names() -> &'static [&'static str];
+
+    /// Each timestamp as a JSON value token (a bare number, a quoted string, or
+    /// `null` for a keep-token), in `field_names` order.
+    fn json_values(&self) -> Vec<String>;
+
+    /// Reconstructs a record from the JSON value tokens produced by
+    /// [`Timestamps::json_values`].
+    fn from_json_values(values: &[&str]) -> Option<Self>
+    where
+        Self: Sized;
 }
 
+// The token a column carries to mean "leave this timestamp unchanged". A
+// `FILE_BASIC_INFO` time field of `0` tells the system to keep that particular
+// timestamp, which is how the keep-token is honored on apply.
+const KEEP_TOKEN: &str = "-";
+
+/// A dumped timestamp column that may instead carry a keep-token (`-`, or an
+/// empty field) meaning "do not touch this timestamp on this file".
+#[derive(Debug)]
+struct Keepable<T>(Option<T>);
+
+impl<T: std::fmt::Display> std::fmt::Display for Keepable<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.0 {
+            Some(v) => write!(f, "{}", v),
+            None => f.write_str(KEEP_TOKEN),
+        }
+    }
+}
+
+impl<T: std::str::FromStr> std::str::FromStr for Keepable<T> {
+    type Err = T::Err;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() || s == KEEP_TOKEN {
+            Ok(Keepable(None))
+        } else {
+            Ok(Keepable(Some(s.parse()?)))
+        }
+    }
+}
+
+// The JSON spelling of a keep-token.
+const KEEP_JSON: &str = "null";
+
 #[derive(Display, FromStr, Debug)]
 #[display("{created}\t{modified}\t{changed}\t{accessed}")]
 struct V0Timestamps {
-    created: i64,
-    modified: i64,
-    changed: i64,
-    accessed: i64,
+    created: Keepable<i64>,
+    modified: Keepable<i64>,
+    changed: Keepable<i64>,
+    accessed: Keepable<i64>,
 }
 
 impl Timestamps for V0Timestamps {
@@ -160,32 +288,816 @@ impl Timestamps for V0Timestamps {
 
     fn get(path: &Path) -> Option<Self> {
         unsafe {
-            get_file_basic_info(path).map(|fi| V0Timestamps {
-                created: *fi.CreationTime.QuadPart(),
-                modified: *fi.LastWriteTime.QuadPart(),
-                accessed: *fi.LastAccessTime.QuadPart(),
-                changed: *fi.ChangeTime.QuadPart(),
+            get_file_basic_info(path).map(|fi| {
+                V0Timestamps::from_filetimes(
+                    *fi.CreationTime.QuadPart(),
+                    *fi.LastWriteTime.QuadPart(),
+                    *fi.ChangeTime.QuadPart(),
+                    *fi.LastAccessTime.QuadPart(),
+                )
             })
         }
     }
 
-    fn set(self, path: &Path) {
+    fn from_filetimes(created: i64, modified: i64, changed: i64, accessed: i64) -> Self {
+        V0Timestamps {
+            created: Keepable(Some(created)),
+            modified: Keepable(Some(modified)),
+            changed: Keepable(Some(changed)),
+            accessed: Keepable(Some(accessed)),
+        }
+    }
+
+    fn set(self, path: &Path) -> io::Result<()> {
+        // A keep-token maps to `0`, which leaves that timestamp untouched.
         unsafe {
             set_file_basic_info(
                 path,
                 FILE_BASIC_INFO {
-                    CreationTime: make_large_integer(self.created),
-                    LastAccessTime: make_large_integer(self.accessed),
-                    LastWriteTime: make_large_integer(self.modified),
-                    ChangeTime: make_large_integer(self.changed),
+                    CreationTime: make_large_integer(self.created.0.unwrap_or(0)),
+                    LastAccessTime: make_large_integer(self.accessed.0.unwrap_or(0)),
+                    LastWriteTime: make_large_integer(self.modified.0.unwrap_or(0)),
+                    ChangeTime: make_large_integer(self.changed.0.unwrap_or(0)),
                     FileAttributes: 0, // keeps original attributes
                 },
             )
         }
     }
+
+    fn field_names() -> &'static [&'static str] {
+        &["created", "modified", "changed", "accessed"]
+    }
+
+    fn json_values(&self) -> Vec<String> {
+        [&self.created, &self.modified, &self.changed, &self.accessed]
+            .iter()
+            .map(|f| match f.0 {
+                Some(v) => v.to_string(),
+                None => KEEP_JSON.to_string(),
+            })
+            .collect()
+    }
+
+    fn from_json_values(values: &[&str]) -> Option<Self> {
+        let parse = |s: &str| -> Option<Keepable<i64>> {
+            if s.is_empty() || s == KEEP_JSON {
+                Some(Keepable(None))
+            } else {
+                Some(Keepable(Some(s.parse().ok()?)))
+            }
+        };
+        Some(V0Timestamps {
+            created: parse(values.first()?)?,
+            modified: parse(values.get(1)?)?,
+            changed: parse(values.get(2)?)?,
+            accessed: parse(values.get(3)?)?,
+        })
+    }
+}
+
+// A Win32 FILETIME counts 100-ns ticks since 1601-01-01 UTC; the Unix epoch
+// sits this many ticks later.
+const FILETIME_UNIX_EPOCH: i64 = 116_444_736_000_000_000;
+const TICKS_PER_SECOND: i64 = 10_000_000;
+
+// Howard Hinnant's civil-from-days: days since 1970-01-01 to (year, month, day).
+fn civil_from_days(z: i64) -> (i64, i64, i64) {
+    let z = z + 719_468;
+    let era = if z >= 0 { z } else { z - 146_096 } / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let y = yoe + era * 400;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    (if m <= 2 { y + 1 } else { y }, m, d)
+}
+
+// The inverse: (year, month, day) to days since 1970-01-01.
+fn days_from_civil(y: i64, m: i64, d: i64) -> i64 {
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = if y >= 0 { y } else { y - 399 } / 400;
+    let yoe = y - era * 400;
+    let doy = (153 * (if m > 2 { m - 3 } else { m + 9 }) + 2) / 5 + d - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+// Formats a FILETIME as an RFC 3339 / ISO-8601 string, keeping the full seven
+// fractional (100-ns) digits so the round-trip is lossless.
+fn filetime_to_rfc3339(ft: i64) -> String {
+    let ticks = ft - FILETIME_UNIX_EPOCH;
+    let secs = ticks.div_euclid(TICKS_PER_SECOND);
+    let frac = ticks.rem_euclid(TICKS_PER_SECOND);
+    let days = secs.div_euclid(86_400);
+    let tod = secs.rem_euclid(86_400);
+    let (y, mo, d) = civil_from_days(days);
+    let (hh, mm, ss) = (tod / 3600, (tod % 3600) / 60, tod % 60);
+    format!("{y:04}-{mo:02}-{d:02}T{hh:02}:{mm:02}:{ss:02}.{frac:07}Z")
+}
+
+// Parses an RFC 3339 string produced by `filetime_to_rfc3339` back into a raw
+// FILETIME. Returns `None` on any structural error.
+fn rfc3339_to_filetime(s: &str) -> Option<i64> {
+    let s = s.trim().trim_end_matches('Z');
+    let (date, time) = s.split_once('T')?;
+    let mut d = date.split('-');
+    let year: i64 = d.next()?.parse().ok()?;
+    let month: i64 = d.next()?.parse().ok()?;
+    let day: i64 = d.next()?.parse().ok()?;
+
+    let mut t = time.split(':');
+    let hour: i64 = t.next()?.parse().ok()?;
+    let minute: i64 = t.next()?.parse().ok()?;
+    let sec_token = t.next()?;
+    let (sec_str, frac_str) = sec_token.split_once('.').unwrap_or((sec_token, ""));
+    let second: i64 = sec_str.parse().ok()?;
+
+    // Pad or trim the fraction to exactly seven 100-ns digits.
+    let mut frac_digits = frac_str.to_string();
+    frac_digits.truncate(7);
+    while frac_digits.len() < 7 {
+        frac_digits.push('0');
+    }
+    let frac: i64 = if frac_digits.is_empty() {
+        0
+    } else {
+        frac_digits.parse().ok()?
+    };
+
+    let days = days_from_civil(year, month, day);
+    let secs = days * 86_400 + hour * 3600 + minute * 60 + second;
+    Some(secs * TICKS_PER_SECOND + frac + FILETIME_UNIX_EPOCH)
+}
+
+#[derive(Display, FromStr, Debug)]
+#[display("{created}\t{modified}\t{changed}\t{accessed}")]
+struct V1Timestamps {
+    created: Keepable<String>,
+    modified: Keepable<String>,
+    changed: Keepable<String>,
+    accessed: Keepable<String>,
+}
+
+// Resolves one ISO-8601 column to the raw FILETIME `set` should write: a
+// keep-token becomes `0` (leave unchanged), otherwise the parsed value.
+fn resolve_v1(field: &Keepable<String>) -> Option<i64> {
+    match &field.0 {
+        None => Some(0),
+        Some(s) => rfc3339_to_filetime(s),
+    }
+}
+
+// Resolves the four ISO-8601 timestamp columns and writes them, with the given
+// attributes, to `path`. A keep-token resolves to `0` (leave unchanged); any
+// column that fails to parse aborts the write with an `InvalidData` error for
+// `apply` to collect. Shared by every ISO-8601 record's `set`.
+fn set_resolved(
+    path: &Path,
+    created: &Keepable<String>,
+    modified: &Keepable<String>,
+    changed: &Keepable<String>,
+    accessed: &Keepable<String>,
+    attributes: u32,
+) -> io::Result<()> {
+    match (
+        resolve_v1(created),
+        resolve_v1(modified),
+        resolve_v1(changed),
+        resolve_v1(accessed),
+    ) {
+        (Some(created), Some(modified), Some(changed), Some(accessed)) => unsafe {
+            set_file_basic_info(
+                path,
+                FILE_BASIC_INFO {
+                    CreationTime: make_large_integer(created),
+                    LastAccessTime: make_large_integer(accessed),
+                    LastWriteTime: make_large_integer(modified),
+                    ChangeTime: make_large_integer(changed),
+                    FileAttributes: attributes,
+                },
+            )
+        },
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{}: malformed timestamp", path.display()),
+        )),
+    }
+}
+
+impl Timestamps for V1Timestamps {
+    fn version() -> i32 {
+        1
+    }
+
+    fn header() -> &'static str {
+        "Created\tModified\tChanged\tAccessed"
+    }
+
+    fn get(path: &Path) -> Option<Self> {
+        unsafe {
+            get_file_basic_info(path).map(|fi| {
+                V1Timestamps::from_filetimes(
+                    *fi.CreationTime.QuadPart(),
+                    *fi.LastWriteTime.QuadPart(),
+                    *fi.ChangeTime.QuadPart(),
+                    *fi.LastAccessTime.QuadPart(),
+                )
+            })
+        }
+    }
+
+    fn set(self, path: &Path) -> io::Result<()> {
+        // `0` attributes keep the file's originals.
+        set_resolved(
+            path,
+            &self.created,
+            &self.modified,
+            &self.changed,
+            &self.accessed,
+            0,
+        )
+    }
+
+    fn from_filetimes(created: i64, modified: i64, changed: i64, accessed: i64) -> Self {
+        V1Timestamps {
+            created: Keepable(Some(filetime_to_rfc3339(created))),
+            modified: Keepable(Some(filetime_to_rfc3339(modified))),
+            changed: Keepable(Some(filetime_to_rfc3339(changed))),
+            accessed: Keepable(Some(filetime_to_rfc3339(accessed))),
+        }
+    }
+
+    fn field_names() -> &'static [&'static str] {
+        &["created", "modified", "changed", "accessed"]
+    }
+
+    fn json_values(&self) -> Vec<String> {
+        [&self.created, &self.modified, &self.changed, &self.accessed]
+            .iter()
+            .map(|f| match &f.0 {
+                Some(v) => json_string(v),
+                None => KEEP_JSON.to_string(),
+            })
+            .collect()
+    }
+
+    fn from_json_values(values: &[&str]) -> Option<Self> {
+        let parse = |s: &str| -> Keepable<String> {
+            if s.is_empty() || s == KEEP_JSON {
+                Keepable(None)
+            } else {
+                Keepable(Some(s.to_string()))
+            }
+        };
+        Some(V1Timestamps {
+            created: parse(values.first()?),
+            modified: parse(values.get(1)?),
+            changed: parse(values.get(2)?),
+            accessed: parse(values.get(3)?),
+        })
+    }
+}
+
+// The Win32 `FileAttributes` flags, rendered as a `|`-separated symbolic set so
+// the dumps stay grepable instead of carrying a bare hex number.
+#[derive(Debug, Clone, Copy)]
+struct Attributes(u32);
+
+const ATTRIBUTE_FLAGS: &[(&str, u32)] = &[
+    ("READONLY", FILE_ATTRIBUTE_READONLY),
+    ("HIDDEN", FILE_ATTRIBUTE_HIDDEN),
+    ("SYSTEM", FILE_ATTRIBUTE_SYSTEM),
+    ("DIRECTORY", FILE_ATTRIBUTE_DIRECTORY),
+    ("ARCHIVE", FILE_ATTRIBUTE_ARCHIVE),
+    ("NORMAL", FILE_ATTRIBUTE_NORMAL),
+    ("TEMPORARY", FILE_ATTRIBUTE_TEMPORARY),
+    ("SPARSE_FILE", FILE_ATTRIBUTE_SPARSE_FILE),
+    ("REPARSE_POINT", FILE_ATTRIBUTE_REPARSE_POINT),
+    ("COMPRESSED", FILE_ATTRIBUTE_COMPRESSED),
+    ("OFFLINE", FILE_ATTRIBUTE_OFFLINE),
+    ("NOT_CONTENT_INDEXED", FILE_ATTRIBUTE_NOT_CONTENT_INDEXED),
+    ("ENCRYPTED", FILE_ATTRIBUTE_ENCRYPTED),
+];
+
+impl std::fmt::Display for Attributes {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut remaining = self.0;
+        let mut parts = Vec::new();
+        for (name, bit) in ATTRIBUTE_FLAGS {
+            if remaining & bit != 0 {
+                parts.push((*name).to_string());
+                remaining &= !bit;
+            }
+        }
+        // Any bits we don't have a name for are kept as hex so the round-trip
+        // stays lossless.
+        if remaining != 0 {
+            parts.push(format!("0x{:x}", remaining));
+        }
+        if parts.is_empty() {
+            f.write_str("NONE")
+        } else {
+            write!(f, "{}", parts.join("|"))
+        }
+    }
+}
+
+impl std::str::FromStr for Attributes {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut bits = 0u32;
+        for token in s.split('|') {
+            let token = token.trim();
+            if token.is_empty() || token == "NONE" {
+                continue;
+            }
+            if let Some(hex) = token.strip_prefix("0x") {
+                bits |= u32::from_str_radix(hex, 16).map_err(|e| e.to_string())?;
+            } else {
+                match ATTRIBUTE_FLAGS
+                    .iter()
+                    .find(|(name, _)| name.eq_ignore_ascii_case(token))
+                {
+                    Some((_, bit)) => bits |= bit,
+                    None => return Err(format!("unknown attribute flag: {}", token)),
+                }
+            }
+        }
+        Ok(Attributes(bits))
+    }
+}
+
+// The attribute bits `SetFileInformationByHandle` actually honors. COMPRESSED
+// (needs `FSCTL_SET_COMPRESSION`), ENCRYPTED (needs `EncryptFile`), and the
+// kind-describing SPARSE_FILE/DIRECTORY/REPARSE_POINT bits can't be set through
+// this API, so they are captured in the dump for reference but dropped on apply.
+const SETTABLE_ATTRIBUTES: u32 = FILE_ATTRIBUTE_READONLY
+    | FILE_ATTRIBUTE_HIDDEN
+    | FILE_ATTRIBUTE_SYSTEM
+    | FILE_ATTRIBUTE_ARCHIVE
+    | FILE_ATTRIBUTE_NORMAL
+    | FILE_ATTRIBUTE_TEMPORARY
+    | FILE_ATTRIBUTE_NOT_CONTENT_INDEXED
+    | FILE_ATTRIBUTE_OFFLINE;
+
+// Resolves the attribute column `set` should write. The keep-token leaves `0`,
+// which tells the system to keep the file's current attributes; otherwise only
+// the settable bits are applied, and a snapshot with no settable bits maps to
+// `FILE_ATTRIBUTE_NORMAL` so a flag set since the dump is cleared rather than
+// silently retained.
+fn resolve_attributes(field: &Keepable<Attributes>) -> u32 {
+    match field.0 {
+        None => 0,
+        Some(Attributes(bits)) => match bits & SETTABLE_ATTRIBUTES {
+            0 => FILE_ATTRIBUTE_NORMAL,
+            settable => settable,
+        },
+    }
+}
+
+// The reparse tag identifies what kind of reparse point an entry is, rendered
+// symbolically like [`Attributes`] so a junction is distinguishable from a
+// symlink in the dump. A regular (non-reparse) entry carries `NONE`.
+#[derive(Debug, Clone, Copy)]
+struct ReparseTag(u32);
+
+const REPARSE_TAGS: &[(&str, u32)] = &[
+    ("SYMLINK", IO_REPARSE_TAG_SYMLINK),
+    ("MOUNT_POINT", IO_REPARSE_TAG_MOUNT_POINT),
+];
+
+impl std::fmt::Display for ReparseTag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.0 == 0 {
+            return f.write_str("NONE");
+        }
+        match REPARSE_TAGS.iter().find(|(_, tag)| *tag == self.0) {
+            Some((name, _)) => f.write_str(name),
+            // Unknown tags keep their raw hex so the round-trip stays lossless.
+            None => write!(f, "0x{:x}", self.0),
+        }
+    }
+}
+
+impl std::str::FromStr for ReparseTag {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.is_empty() || s == "NONE" {
+            return Ok(ReparseTag(0));
+        }
+        if let Some(hex) = s.strip_prefix("0x") {
+            return u32::from_str_radix(hex, 16)
+                .map(ReparseTag)
+                .map_err(|e| e.to_string());
+        }
+        match REPARSE_TAGS
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(s))
+        {
+            Some((_, tag)) => Ok(ReparseTag(*tag)),
+            None => Err(format!("unknown reparse tag: {}", s)),
+        }
+    }
+}
+
+#[derive(Display, FromStr, Debug)]
+#[display("{created}\t{modified}\t{changed}\t{accessed}\t{attributes}")]
+struct V2Timestamps {
+    created: Keepable<String>,
+    modified: Keepable<String>,
+    changed: Keepable<String>,
+    accessed: Keepable<String>,
+    attributes: Keepable<Attributes>,
+}
+
+impl Timestamps for V2Timestamps {
+    fn version() -> i32 {
+        2
+    }
+
+    fn header() -> &'static str {
+        "Created\tModified\tChanged\tAccessed\tAttributes"
+    }
+
+    fn get(path: &Path) -> Option<Self> {
+        unsafe {
+            get_file_basic_info(path).map(|fi| V2Timestamps {
+                created: Keepable(Some(filetime_to_rfc3339(*fi.CreationTime.QuadPart()))),
+                modified: Keepable(Some(filetime_to_rfc3339(*fi.LastWriteTime.QuadPart()))),
+                changed: Keepable(Some(filetime_to_rfc3339(*fi.ChangeTime.QuadPart()))),
+                accessed: Keepable(Some(filetime_to_rfc3339(*fi.LastAccessTime.QuadPart()))),
+                attributes: Keepable(Some(Attributes(fi.FileAttributes))),
+            })
+        }
+    }
+
+    fn set(self, path: &Path) -> io::Result<()> {
+        let attributes = resolve_attributes(&self.attributes);
+        set_resolved(
+            path,
+            &self.created,
+            &self.modified,
+            &self.changed,
+            &self.accessed,
+            attributes,
+        )
+    }
+
+    // The MFT path only recovers times, so attributes default to the keep-token.
+    fn from_filetimes(created: i64, modified: i64, changed: i64, accessed: i64) -> Self {
+        V2Timestamps {
+            created: Keepable(Some(filetime_to_rfc3339(created))),
+            modified: Keepable(Some(filetime_to_rfc3339(modified))),
+            changed: Keepable(Some(filetime_to_rfc3339(changed))),
+            accessed: Keepable(Some(filetime_to_rfc3339(accessed))),
+            attributes: Keepable(None),
+        }
+    }
+
+    fn field_names() -> &'static [&'static str] {
+        &["created", "modified", "changed", "accessed", "attributes"]
+    }
+
+    fn json_values(&self) -> Vec<String> {
+        let time = |f: &Keepable<String>| match &f.0 {
+            Some(v) => json_string(v),
+            None => KEEP_JSON.to_string(),
+        };
+        vec![
+            time(&self.created),
+            time(&self.modified),
+            time(&self.changed),
+            time(&self.accessed),
+            match &self.attributes.0 {
+                Some(a) => json_string(&a.to_string()),
+                None => KEEP_JSON.to_string(),
+            },
+        ]
+    }
+
+    fn from_json_values(values: &[&str]) -> Option<Self> {
+        let time = |s: &str| -> Keepable<String> {
+            if s.is_empty() || s == KEEP_JSON {
+                Keepable(None)
+            } else {
+                Keepable(Some(s.to_string()))
+            }
+        };
+        let attrs = values.get(4).copied().unwrap_or("");
+        let attributes = if attrs.is_empty() || attrs == KEEP_JSON {
+            Keepable(None)
+        } else {
+            Keepable(Some(attrs.parse().ok()?))
+        };
+        Some(V2Timestamps {
+            created: time(values.first()?),
+            modified: time(values.get(1)?),
+            changed: time(values.get(2)?),
+            accessed: time(values.get(3)?),
+            attributes,
+        })
+    }
+}
+
+#[derive(Display, FromStr, Debug)]
+#[display("{created}\t{modified}\t{changed}\t{accessed}\t{attributes}\t{reparse_tag}")]
+struct V3Timestamps {
+    created: Keepable<String>,
+    modified: Keepable<String>,
+    changed: Keepable<String>,
+    accessed: Keepable<String>,
+    attributes: Keepable<Attributes>,
+    // Informational only: the tag is recorded so restore can tell a junction
+    // from a symlink from a regular file, but Win32 offers no way to re-stamp
+    // it, so `set` leaves it alone.
+    reparse_tag: ReparseTag,
+}
+
+impl Timestamps for V3Timestamps {
+    fn version() -> i32 {
+        3
+    }
+
+    fn header() -> &'static str {
+        "Created\tModified\tChanged\tAccessed\tAttributes\tReparseTag"
+    }
+
+    fn get(path: &Path) -> Option<Self> {
+        unsafe {
+            let (fi, ti) = get_file_basic_and_tag(path)?;
+            Some(V3Timestamps {
+                created: Keepable(Some(filetime_to_rfc3339(*fi.CreationTime.QuadPart()))),
+                modified: Keepable(Some(filetime_to_rfc3339(*fi.LastWriteTime.QuadPart()))),
+                changed: Keepable(Some(filetime_to_rfc3339(*fi.ChangeTime.QuadPart()))),
+                accessed: Keepable(Some(filetime_to_rfc3339(*fi.LastAccessTime.QuadPart()))),
+                attributes: Keepable(Some(Attributes(fi.FileAttributes))),
+                reparse_tag: ReparseTag(ti.ReparseTag),
+            })
+        }
+    }
+
+    fn set(self, path: &Path) -> io::Result<()> {
+        // The reparse tag is never written back.
+        let attributes = resolve_attributes(&self.attributes);
+        set_resolved(
+            path,
+            &self.created,
+            &self.modified,
+            &self.changed,
+            &self.accessed,
+            attributes,
+        )
+    }
+
+    // The MFT path recovers neither attributes nor a reparse tag, so both
+    // default to their empty spellings.
+    fn from_filetimes(created: i64, modified: i64, changed: i64, accessed: i64) -> Self {
+        V3Timestamps {
+            created: Keepable(Some(filetime_to_rfc3339(created))),
+            modified: Keepable(Some(filetime_to_rfc3339(modified))),
+            changed: Keepable(Some(filetime_to_rfc3339(changed))),
+            accessed: Keepable(Some(filetime_to_rfc3339(accessed))),
+            attributes: Keepable(None),
+            reparse_tag: ReparseTag(0),
+        }
+    }
+
+    fn field_names() -> &'static [&'static str] {
+        &[
+            "created",
+            "modified",
+            "changed",
+            "accessed",
+            "attributes",
+            "reparse_tag",
+        ]
+    }
+
+    fn json_values(&self) -> Vec<String> {
+        let time = |f: &Keepable<String>| match &f.0 {
+            Some(v) => json_string(v),
+            None => KEEP_JSON.to_string(),
+        };
+        vec![
+            time(&self.created),
+            time(&self.modified),
+            time(&self.changed),
+            time(&self.accessed),
+            match &self.attributes.0 {
+                Some(a) => json_string(&a.to_string()),
+                None => KEEP_JSON.to_string(),
+            },
+            json_string(&self.reparse_tag.to_string()),
+        ]
+    }
+
+    fn from_json_values(values: &[&str]) -> Option<Self> {
+        let time = |s: &str| -> Keepable<String> {
+            if s.is_empty() || s == KEEP_JSON {
+                Keepable(None)
+            } else {
+                Keepable(Some(s.to_string()))
+            }
+        };
+        let attrs = values.get(4).copied().unwrap_or("");
+        let attributes = if attrs.is_empty() || attrs == KEEP_JSON {
+            Keepable(None)
+        } else {
+            Keepable(Some(attrs.parse().ok()?))
+        };
+        let reparse_tag = values.get(5).copied().unwrap_or("").parse().ok()?;
+        Some(V3Timestamps {
+            created: time(values.first()?),
+            modified: time(values.get(1)?),
+            changed: time(values.get(2)?),
+            accessed: time(values.get(3)?),
+            attributes,
+            reparse_tag,
+        })
+    }
 }
 // ---------------
 
+// Output formats
+// --------------
+
+/// Serialization backend for `dump` and `apply`. The TSV form keeps the
+/// original `Version`-header layout; the JSON forms carry the version as a
+/// field instead and are consumable by other tooling.
+#[derive(Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+enum Format {
+    #[default]
+    Tsv,
+    /// A single array with one object per file.
+    Json,
+    /// One object per line, so huge trees stream without buffering.
+    Ndjson,
+}
+
+// Escapes a string as a JSON string literal, including the surrounding quotes.
+// Windows paths are full of backslashes, so `\` escaping matters here.
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+// Renders one file as a JSON object: the version, the path, then each named
+// timestamp field.
+fn json_object<V: Timestamps>(path: &Path, ts: &V) -> String {
+    let mut out = format!(
+        "{{\"version\":{},\"path\":{}",
+        V::version(),
+        json_string(&path.display().to_string())
+    );
+    for (name, value) in V::field_names().iter().zip(ts.json_values()) {
+        out.push_str(&format!(",{}:{}", json_string(name), value));
+    }
+    out.push('}');
+    out
+}
+
+// Decodes a JSON string literal starting at `start` (which must index the
+// opening quote), returning the unescaped contents and the index just past the
+// closing quote.
+fn parse_json_string(s: &str, start: usize) -> (String, usize) {
+    let bytes = s.as_bytes();
+    let mut out = String::new();
+    let mut i = start + 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'"' => return (out, i + 1),
+            b'\\' if i + 1 < bytes.len() => {
+                i += 1;
+                match bytes[i] {
+                    b'"' => out.push('"'),
+                    b'\\' => out.push('\\'),
+                    b'/' => out.push('/'),
+                    b'n' => out.push('\n'),
+                    b'r' => out.push('\r'),
+                    b't' => out.push('\t'),
+                    b'u' => {
+                        let hex = &s[i + 1..i + 5];
+                        if let Ok(cp) = u32::from_str_radix(hex, 16) {
+                            if let Some(c) = char::from_u32(cp) {
+                                out.push(c);
+                            }
+                        }
+                        i += 4;
+                    }
+                    other => out.push(other as char),
+                }
+                i += 1;
+            }
+            _ => {
+                // Copy a whole UTF-8 character, not just one byte.
+                let ch = s[i..].chars().next().unwrap();
+                out.push(ch);
+                i += ch.len_utf8();
+            }
+        }
+    }
+    (out, i)
+}
+
+// Parses one flat JSON object (our own dump output, not arbitrary JSON) into a
+// map of key to decoded value. String values are unescaped; numbers are kept as
+// their literal token.
+fn parse_json_object(s: &str) -> HashMap<String, String> {
+    let mut map = HashMap::new();
+    let bytes = s.as_bytes();
+    let mut i = match s.find('{') {
+        Some(pos) => pos + 1,
+        None => return map,
+    };
+    loop {
+        while i < bytes.len() && (bytes[i].is_ascii_whitespace() || bytes[i] == b',') {
+            i += 1;
+        }
+        if i >= bytes.len() || bytes[i] == b'}' {
+            break;
+        }
+        if bytes[i] != b'"' {
+            break;
+        }
+        let (key, next) = parse_json_string(s, i);
+        i = next;
+        while i < bytes.len() && (bytes[i].is_ascii_whitespace() || bytes[i] == b':') {
+            i += 1;
+        }
+        if i >= bytes.len() {
+            break;
+        }
+        if bytes[i] == b'"' {
+            let (value, next) = parse_json_string(s, i);
+            i = next;
+            map.insert(key, value);
+        } else {
+            let start = i;
+            while i < bytes.len() && bytes[i] != b',' && bytes[i] != b'}' {
+                i += 1;
+            }
+            map.insert(key, s[start..i].trim().to_string());
+        }
+    }
+    map
+}
+
+// Collects the dump rows and writes them in the requested format. TSV and
+// NDJSON stream row by row; the JSON array is assembled and written at the end.
+struct DumpSink<V: Timestamps> {
+    format: Format,
+    json: Vec<String>,
+    _marker: PhantomData<V>,
+}
+
+impl<V: Timestamps> DumpSink<V> {
+    fn new(format: Format) -> Self {
+        if format == Format::Tsv {
+            println!("{}{}", HEADER_PREFIX, V::version());
+            println!("{}", column_header::<V>());
+        }
+        DumpSink {
+            format,
+            json: Vec::new(),
+            _marker: PhantomData,
+        }
+    }
+
+    fn row(&mut self, path: &Path, ts: &V) {
+        match self.format {
+            Format::Tsv => println!("{}\t{}", path.display(), ts),
+            Format::Ndjson => println!("{}", json_object(path, ts)),
+            Format::Json => self.json.push(json_object(path, ts)),
+        }
+    }
+
+    fn finish(self) {
+        if self.format == Format::Json {
+            println!("[\n{}\n]", self.json.join(",\n"));
+        }
+    }
+}
+
+// --------------
+
 // Top-level functions
 // -------------------
 
@@ -193,9 +1105,28 @@ fn column_header<V: Timestamps>() -> String {
     format!("Path\t{}", V::header())
 }
 
-fn dump<V: Timestamps>(root: &Path) {
-    println!("{}{}", HEADER_PREFIX, V::version());
-    println!("{}", column_header::<V>());
+// Dumps a whole volume's timestamps by parsing the NTFS `$MFT` directly,
+// emitting the same `Path\t...` rows as the `CreateFileW`-based `dump` so the
+// two outputs are interchangeable. Requires elevation.
+fn dump_from_mft<V: Timestamps>(volume: &Path, format: Format) {
+    let mut sink = DumpSink::<V>::new(format);
+
+    let entries = match mft::read_volume(volume) {
+        Ok(entries) => entries,
+        Err(err) => {
+            eprintln!("{}: error reading MFT: {}", volume.display(), err);
+            return;
+        }
+    };
+    for entry in entries {
+        let ts = V::from_filetimes(entry.created, entry.modified, entry.changed, entry.accessed);
+        sink.row(&entry.path, &ts);
+    }
+    sink.finish();
+}
+
+fn dump<V: Timestamps>(root: &Path, format: Format) {
+    let mut sink = DumpSink::<V>::new(format);
 
     for entry in WalkDir::new(root) {
         if let Err(err) = entry {
@@ -204,36 +1135,193 @@ fn dump<V: Timestamps>(root: &Path) {
         }
         let entry = entry.unwrap();
 
-        match V::get(&entry.path()) {
-            None => continue,
-            Some(ts) => println!("{}\t{}", entry.path().display(), ts),
+        if let Some(ts) = V::get(&entry.path()) {
+            sink.row(&entry.path(), &ts);
         }
     }
+    sink.finish();
 }
 
-fn apply<V: Timestamps, T: std::io::BufRead>(mut lines: std::io::Lines<T>)
+// Restores every row, skipping (and counting) the ones that fail to parse or
+// whose file can't be written, and returns how many failed so the caller can
+// exit non-zero. A single bad row no longer aborts the whole restore.
+fn apply<V: Timestamps, T: std::io::BufRead>(mut lines: std::io::Lines<T>) -> usize
 where
-    <V as std::str::FromStr>::Err: std::fmt::Debug,
+    <V as std::str::FromStr>::Err: std::fmt::Display,
 {
     let column_header = column_header::<V>();
-    assert_eq!(lines.next().unwrap().unwrap(), column_header);
-    for line in lines {
-        let line = line.unwrap();
-        let (path, timestamps) = line.split_once('\t').unwrap();
-        timestamps.parse::<V>().unwrap().set(Path::new(path));
+    match lines.next() {
+        Some(Ok(header)) if header == column_header => {}
+        Some(Ok(header)) => {
+            eprintln!("unexpected column header: {:?}", header);
+            return 1;
+        }
+        _ => {
+            eprintln!("missing column header");
+            return 1;
+        }
+    }
+
+    let mut failures = 0;
+    // Lines 1 and 2 were the version and column headers, so data rows are
+    // numbered from 3 to match what a user sees in an editor.
+    for (offset, line) in lines.enumerate() {
+        let lineno = offset + 3;
+        let line = match line {
+            Ok(line) => line,
+            Err(err) => {
+                eprintln!("line {}: read error: {}", lineno, err);
+                failures += 1;
+                continue;
+            }
+        };
+        let Some((path, timestamps)) = line.split_once('\t') else {
+            eprintln!("line {}: missing tab separator", lineno);
+            failures += 1;
+            continue;
+        };
+        let ts = match timestamps.parse::<V>() {
+            Ok(ts) => ts,
+            Err(err) => {
+                eprintln!("line {}: {}: parse error: {}", lineno, path, err);
+                failures += 1;
+                continue;
+            }
+        };
+        if let Err(err) = ts.set(Path::new(path)) {
+            eprintln!("{}: {}", path, err);
+            failures += 1;
+        }
+    }
+    failures
+}
+
+// Pulls the named timestamp fields out of a parsed object and applies them,
+// returning `1` if the record was malformed or its file couldn't be written.
+fn apply_json_row<V: Timestamps>(obj: &HashMap<String, String>, path: &str) -> usize {
+    let values: Vec<&str> = V::field_names()
+        .iter()
+        .map(|name| obj.get(*name).map(String::as_str).unwrap_or(""))
+        .collect();
+    match V::from_json_values(&values) {
+        Some(ts) => match ts.set(Path::new(path)) {
+            Ok(()) => 0,
+            Err(err) => {
+                eprintln!("{}: {}", path, err);
+                1
+            }
+        },
+        None => {
+            eprintln!("{}: malformed record", path);
+            1
+        }
+    }
+}
+
+// Restores one JSON/NDJSON object, dispatching on its embedded `version` field.
+// Returns the number of failures (0 or 1) for this object.
+fn apply_json_object(obj: &HashMap<String, String>) -> usize {
+    let path = match obj.get("path") {
+        Some(p) => p,
+        None => {
+            eprintln!("record without a path");
+            return 1;
+        }
+    };
+    let version = obj.get("version").and_then(|v| v.parse::<i32>().ok());
+    match version {
+        Some(0) => apply_json_row::<V0Timestamps>(obj, path),
+        Some(1) => apply_json_row::<V1Timestamps>(obj, path),
+        Some(2) => apply_json_row::<V2Timestamps>(obj, path),
+        Some(3) => apply_json_row::<V3Timestamps>(obj, path),
+        _ => {
+            eprintln!("{}: unknown version: {:?}", path, version);
+            1
+        }
+    }
+}
+
+// Applies a JSON array or NDJSON stream. Both put exactly one object per line,
+// so the array brackets and trailing commas are simply stripped per line.
+fn apply_json<T: std::io::BufRead>(file: T) -> usize {
+    let mut failures = 0;
+    for (offset, line) in file.lines().enumerate() {
+        let line = match line {
+            Ok(line) => line,
+            Err(err) => {
+                eprintln!("line {}: read error: {}", offset + 1, err);
+                failures += 1;
+                continue;
+            }
+        };
+        let trimmed = line
+            .trim()
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .trim()
+            .trim_end_matches(',')
+            .trim();
+        if !trimmed.starts_with('{') {
+            continue;
+        }
+        failures += apply_json_object(&parse_json_object(trimmed));
     }
+    failures
 }
 
-fn apply_any<T: std::io::BufRead>(mut file: T) {
+fn apply_tsv<T: std::io::BufRead>(mut file: T) -> usize {
     let mut version: [u8; HEADER_PREFIX.len()] = [0; HEADER_PREFIX.len()];
-    file.read_exact(&mut version).unwrap();
-    assert_eq!(version, HEADER_PREFIX.as_bytes());
+    if let Err(err) = file.read_exact(&mut version) {
+        eprintln!("error reading version header: {}", err);
+        return 1;
+    }
+    if version != HEADER_PREFIX.as_bytes() {
+        eprintln!("missing {:?} header", HEADER_PREFIX);
+        return 1;
+    }
 
     let mut timestamps = file.lines();
-    let version = timestamps.next().unwrap().unwrap().parse::<i32>().unwrap();
+    let version = match timestamps.next() {
+        Some(Ok(line)) => match line.trim().parse::<i32>() {
+            Ok(version) => version,
+            Err(_) => {
+                eprintln!("malformed version number: {:?}", line);
+                return 1;
+            }
+        },
+        _ => {
+            eprintln!("missing version number");
+            return 1;
+        }
+    };
     match version {
         0 => apply::<V0Timestamps, T>(timestamps),
-        _ => eprintln!("unknown version: {}", version),
+        1 => apply::<V1Timestamps, T>(timestamps),
+        2 => apply::<V2Timestamps, T>(timestamps),
+        3 => apply::<V3Timestamps, T>(timestamps),
+        _ => {
+            eprintln!("unknown version: {}", version);
+            1
+        }
+    }
+}
+
+// Restores from stdin and returns the number of files that could not be
+// applied, so `main` can exit non-zero without ever panicking on bad input.
+fn apply_any<T: std::io::BufRead>(mut file: T, format: Option<Format>) -> usize {
+    // Without an explicit `--format`, sniff the leading byte: `[` starts a JSON
+    // array, `{` an NDJSON object, anything else the TSV `Version` header.
+    let format = match format {
+        Some(f) => f,
+        None => match file.fill_buf().ok().and_then(|b| b.first().copied()) {
+            Some(b'[') => Format::Json,
+            Some(b'{') => Format::Ndjson,
+            _ => Format::Tsv,
+        },
+    };
+    match format {
+        Format::Tsv => apply_tsv(file),
+        Format::Json | Format::Ndjson => apply_json(file),
     }
 }
 // -------------------
@@ -256,10 +1344,40 @@ enum CliCommand {
     Dump {
         /// Root of the path to be dumped
         root: PathBuf,
+
+        /// Read timestamps for the whole volume by parsing the NTFS $MFT
+        /// directly instead of opening every file. `root` is then interpreted
+        /// as a drive-letter root such as `C:\`. Requires elevation.
+        #[arg(long)]
+        from_mft: bool,
+
+        /// Output format.
+        #[arg(long, value_enum, default_value_t = Format::Tsv)]
+        format: Format,
+
+        /// Record version: 0 stores raw 100-ns FILETIME integers, 1 stores
+        /// human-readable ISO-8601 strings, 2 adds file attributes, 3 also adds
+        /// the reparse tag.
+        #[arg(long = "version", default_value_t = 0)]
+        version: i32,
+
+        /// Follow reparse points (symlinks, junctions) to their target instead
+        /// of operating on the link itself.
+        #[arg(long)]
+        follow: bool,
     },
 
     /// Applies previously dumped timestamps from stdin.
-    Apply,
+    Apply {
+        /// Input format. Auto-detected from the first byte when omitted.
+        #[arg(long, value_enum)]
+        format: Option<Format>,
+
+        /// Follow reparse points (symlinks, junctions) to their target instead
+        /// of operating on the link itself.
+        #[arg(long)]
+        follow: bool,
+    },
 }
 // ------------
 
@@ -267,7 +1385,98 @@ fn main() {
     let args = Cli::parse();
 
     match args.command {
-        CliCommand::Dump { root } => dump::<V0Timestamps>(&root),
-        CliCommand::Apply => apply_any(io::stdin().lock()),
+        CliCommand::Dump {
+            root,
+            from_mft,
+            format,
+            version,
+            follow,
+        } => {
+            FOLLOW_REPARSE.store(follow, Ordering::Relaxed);
+            match (from_mft, version) {
+                (true, 3) => dump_from_mft::<V3Timestamps>(&root, format),
+                (true, 2) => dump_from_mft::<V2Timestamps>(&root, format),
+                (true, 1) => dump_from_mft::<V1Timestamps>(&root, format),
+                (true, _) => dump_from_mft::<V0Timestamps>(&root, format),
+                (false, 3) => dump::<V3Timestamps>(&root, format),
+                (false, 2) => dump::<V2Timestamps>(&root, format),
+                (false, 1) => dump::<V1Timestamps>(&root, format),
+                (false, _) => dump::<V0Timestamps>(&root, format),
+            }
+        }
+        CliCommand::Apply { format, follow } => {
+            FOLLOW_REPARSE.store(follow, Ordering::Relaxed);
+            let failures = apply_any(io::stdin().lock(), format);
+            if failures > 0 {
+                eprintln!("{} file(s) could not be applied", failures);
+                std::process::exit(1);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filetime_round_trips_losslessly() {
+        // Raw FILETIME values: the epoch boundaries and a pre-1970 value that
+        // drives the negative `div_euclid`/`rem_euclid` path.
+        for ft in [
+            0,                               // 1601-01-01, the FILETIME origin
+            FILETIME_UNIX_EPOCH,             // 1970-01-01
+            FILETIME_UNIX_EPOCH - 1,         // the last tick before the Unix epoch
+            FILETIME_UNIX_EPOCH - 123_456_789,
+            FILETIME_UNIX_EPOCH + 1,
+        ] {
+            assert_eq!(rfc3339_to_filetime(&filetime_to_rfc3339(ft)), Some(ft));
+        }
+    }
+
+    #[test]
+    fn filetime_keeps_full_subsecond_precision() {
+        // All seven 100-ns fractional digits must survive the round-trip.
+        let s = "2021-03-04T05:06:56.1234567Z";
+        let ft = rfc3339_to_filetime(s).unwrap();
+        assert_eq!(filetime_to_rfc3339(ft), s);
+
+        assert_eq!(filetime_to_rfc3339(0), "1601-01-01T00:00:00.0000000Z");
+        assert_eq!(
+            filetime_to_rfc3339(FILETIME_UNIX_EPOCH),
+            "1970-01-01T00:00:00.0000000Z"
+        );
+    }
+
+    #[test]
+    fn json_string_round_trips_paths_and_controls() {
+        for original in [
+            r"C:\Users\me\file.txt",
+            "quote\"backslash\\tab\ttab",
+            "newline\r\nand\u{0001}control",
+            "plain",
+        ] {
+            let literal = json_string(original);
+            let (decoded, end) = parse_json_string(&literal, 0);
+            assert_eq!(decoded, original);
+            assert_eq!(end, literal.len());
+        }
+    }
+
+    #[test]
+    fn parse_json_object_reads_our_own_dump() {
+        let obj = parse_json_object(
+            r#"{"version":2,"path":"C:\\dir\\file.txt","attributes":"HIDDEN|READONLY"}"#,
+        );
+        // Numbers keep their literal token; strings are unescaped.
+        assert_eq!(obj.get("version").map(String::as_str), Some("2"));
+        assert_eq!(
+            obj.get("path").map(String::as_str),
+            Some(r"C:\dir\file.txt")
+        );
+        assert_eq!(
+            obj.get("attributes").map(String::as_str),
+            Some("HIDDEN|READONLY")
+        );
     }
 }